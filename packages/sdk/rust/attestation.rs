@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use base64::Engine;
+use serde::Deserialize;
+
+use crate::ingestion::{ingest_plug, AgentContext};
+
+/// A public key accepted by a `TrustStore`, tagged by the algorithm it verifies.
+#[derive(Debug, Clone)]
+pub enum VerifyingKey {
+    Ed25519(ed25519_dalek::VerifyingKey),
+    EcdsaP256(p256::ecdsa::VerifyingKey),
+}
+
+/// Maps `key_id` to the public key an operator has chosen to trust.
+pub type TrustStore = HashMap<String, VerifyingKey>;
+
+#[derive(Debug, Deserialize)]
+struct Attestation {
+    alg: String,
+    key_id: String,
+    sig: String,
+    signed: String,
+}
+
+fn signed_message(
+    signed: &str,
+    world_id: &str,
+    capsule_digest: &str,
+    baseline_merkle_root: &str,
+) -> anyhow::Result<Vec<u8>> {
+    match signed {
+        "baseline_merkle_root" => Ok(baseline_merkle_root.as_bytes().to_vec()),
+        "world_id||capsule_digest" => {
+            Ok(format!("{}||{}", world_id, capsule_digest).into_bytes())
+        }
+        other => anyhow::bail!("unsupported attestation.signed value: {}", other),
+    }
+}
+
+fn verify_signature(key: &VerifyingKey, message: &[u8], sig: &[u8]) -> anyhow::Result<bool> {
+    use ed25519_dalek::Verifier as _;
+
+    Ok(match key {
+        VerifyingKey::Ed25519(vk) => {
+            let sig = ed25519_dalek::Signature::from_slice(sig)
+                .context("malformed ed25519 signature")?;
+            vk.verify(message, &sig).is_ok()
+        }
+        VerifyingKey::EcdsaP256(vk) => {
+            let sig =
+                p256::ecdsa::Signature::from_slice(sig).context("malformed ecdsa-p256 signature")?;
+            vk.verify(message, &sig).is_ok()
+        }
+    })
+}
+
+fn alg_matches(key: &VerifyingKey, alg: &str) -> bool {
+    matches!(
+        (key, alg),
+        (VerifyingKey::Ed25519(_), "ed25519") | (VerifyingKey::EcdsaP256(_), "ecdsa-p256")
+    )
+}
+
+/// Verifies that `attestation` carries a valid signature by a key present in `trust`, over the
+/// canonical bytes it claims to sign. Returns the `key_id` that verified, if any.
+pub fn verify_attestation(
+    attestation: &serde_json::Value,
+    trust: &TrustStore,
+    world_id: &str,
+    capsule_digest: &str,
+    baseline_merkle_root: &str,
+) -> anyhow::Result<Option<String>> {
+    let attestation: Attestation =
+        serde_json::from_value(attestation.clone()).context("malformed attestation")?;
+
+    let key = match trust.get(&attestation.key_id) {
+        Some(key) => key,
+        None => return Ok(None),
+    };
+    if !alg_matches(key, &attestation.alg) {
+        return Ok(None);
+    }
+
+    let sig_bytes = base64::engine::general_purpose::STANDARD
+        .decode(&attestation.sig)
+        .context("attestation.sig is not valid base64")?;
+    let message = signed_message(
+        &attestation.signed,
+        world_id,
+        capsule_digest,
+        baseline_merkle_root,
+    )?;
+
+    Ok(if verify_signature(key, &message, &sig_bytes)? {
+        Some(attestation.key_id)
+    } else {
+        None
+    })
+}
+
+/// Runs the existing `ingest_plug` structural checks and then rejects the plug unless its
+/// `invariant_set.attestation` carries a valid signature from `trust`. This anchors trust in
+/// a caller-supplied key set rather than the payload's self-reported fields.
+pub fn ingest_plug_verified(json: &str, trust: &TrustStore) -> anyhow::Result<AgentContext> {
+    let mut ctx = ingest_plug(json)?;
+
+    let verified = verify_attestation(
+        &ctx.invariants.attestation,
+        trust,
+        &ctx.world_id,
+        &ctx.capsule_digest,
+        &ctx.psp,
+    )?;
+    let key_id = verified.ok_or_else(|| {
+        anyhow::anyhow!(
+            "no valid attestation signature for world_id {}",
+            ctx.world_id
+        )
+    })?;
+
+    ctx.verified_by = vec![key_id];
+    Ok(ctx)
+}
+
+/// An M-of-N signing policy for a world's invariant set, modeled on TUF role metadata: only
+/// `key_ids` are authorized signers, and at least `threshold` distinct ones must produce a
+/// valid signature for the attestation to satisfy the policy.
+#[derive(Debug, Clone)]
+pub struct RolePolicy {
+    pub key_ids: Vec<String>,
+    pub threshold: u32,
+}
+
+/// Maps `world_id` to the `RolePolicy` that governs its invariant-set attestations.
+pub type PolicyStore = HashMap<String, RolePolicy>;
+
+#[derive(Debug, Deserialize)]
+struct SignatureEntry {
+    key_id: String,
+    sig: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ThresholdAttestation {
+    alg: String,
+    signed: String,
+    signatures: Vec<SignatureEntry>,
+}
+
+/// Verifies a `{ alg, signed, signatures: [...] }` attestation against `policy`, returning the
+/// distinct, policy-authorized `key_id`s whose signatures verified. Unknown or duplicate
+/// `key_id`s in the attestation are rejected outright rather than silently ignored.
+fn verify_threshold_attestation(
+    attestation: &serde_json::Value,
+    trust: &TrustStore,
+    policy: &RolePolicy,
+    world_id: &str,
+    capsule_digest: &str,
+    baseline_merkle_root: &str,
+) -> anyhow::Result<Vec<String>> {
+    let attestation: ThresholdAttestation =
+        serde_json::from_value(attestation.clone()).context("malformed attestation")?;
+
+    let message = signed_message(
+        &attestation.signed,
+        world_id,
+        capsule_digest,
+        baseline_merkle_root,
+    )?;
+
+    let mut seen = std::collections::HashSet::new();
+    let mut verified_by = Vec::new();
+    for entry in &attestation.signatures {
+        if !policy.key_ids.iter().any(|id| id == &entry.key_id) {
+            anyhow::bail!("attestation key_id {} is not authorized by policy", entry.key_id);
+        }
+        if !seen.insert(entry.key_id.clone()) {
+            anyhow::bail!("duplicate attestation key_id: {}", entry.key_id);
+        }
+
+        let key = trust
+            .get(&entry.key_id)
+            .ok_or_else(|| anyhow::anyhow!("no trusted key for key_id {}", entry.key_id))?;
+        if !alg_matches(key, &attestation.alg) {
+            continue;
+        }
+        let sig_bytes = base64::engine::general_purpose::STANDARD
+            .decode(&entry.sig)
+            .context("signatures[].sig is not valid base64")?;
+        if verify_signature(key, &message, &sig_bytes)? {
+            verified_by.push(entry.key_id.clone());
+        }
+    }
+
+    Ok(verified_by)
+}
+
+/// Like `ingest_plug_verified`, but requires a quorum of `threshold` distinct, policy-authorized
+/// signers (looked up by `world_id` in `policies`) rather than any single signature. This gives
+/// operators separation-of-duties: no single compromised key can mint a valid plug.
+pub fn ingest_plug_verified_threshold(
+    json: &str,
+    trust: &TrustStore,
+    policies: &PolicyStore,
+) -> anyhow::Result<AgentContext> {
+    let mut ctx = ingest_plug(json)?;
+
+    let policy = policies
+        .get(&ctx.world_id)
+        .ok_or_else(|| anyhow::anyhow!("no role policy for world_id {}", ctx.world_id))?;
+
+    let verified_by = verify_threshold_attestation(
+        &ctx.invariants.attestation,
+        trust,
+        policy,
+        &ctx.world_id,
+        &ctx.capsule_digest,
+        &ctx.psp,
+    )?;
+    if verified_by.len() < policy.threshold as usize {
+        anyhow::bail!(
+            "attestation quorum not met for world_id {}: {} of {} required signers",
+            ctx.world_id,
+            verified_by.len(),
+            policy.threshold
+        );
+    }
+
+    ctx.verified_by = verified_by;
+    Ok(ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{plug_json, BASELINE_ROOT};
+
+    fn ed25519_entry(key_id: &str) -> (String, VerifyingKey, ed25519_dalek::SigningKey) {
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let verifying_key = VerifyingKey::Ed25519(signing_key.verifying_key());
+        (key_id.to_string(), verifying_key, signing_key)
+    }
+
+    fn sign_ed25519(signing_key: &ed25519_dalek::SigningKey, message: &[u8]) -> String {
+        use ed25519_dalek::Signer as _;
+        let sig = signing_key.sign(message);
+        base64::engine::general_purpose::STANDARD.encode(sig.to_bytes())
+    }
+
+    #[test]
+    fn ingest_plug_verified_accepts_valid_signature() {
+        let (key_id, vk, sk) = ed25519_entry("key-a");
+        let mut trust = TrustStore::new();
+        trust.insert(key_id.clone(), vk);
+
+        let sig = sign_ed25519(&sk, BASELINE_ROOT.as_bytes());
+        let json = plug_json(serde_json::json!({
+            "alg": "ed25519",
+            "key_id": key_id,
+            "sig": sig,
+            "signed": "baseline_merkle_root",
+        }));
+
+        let ctx = ingest_plug_verified(&json, &trust).unwrap();
+        assert_eq!(ctx.verified_by, vec!["key-a".to_string()]);
+    }
+
+    #[test]
+    fn ingest_plug_verified_rejects_tampered_signature() {
+        let (key_id, vk, sk) = ed25519_entry("key-a");
+        let mut trust = TrustStore::new();
+        trust.insert(key_id.clone(), vk);
+
+        let mut sig = sign_ed25519(&sk, BASELINE_ROOT.as_bytes());
+        sig.replace_range(0..1, if sig.starts_with('A') { "B" } else { "A" });
+        let json = plug_json(serde_json::json!({
+            "alg": "ed25519",
+            "key_id": key_id,
+            "sig": sig,
+            "signed": "baseline_merkle_root",
+        }));
+
+        assert!(ingest_plug_verified(&json, &trust).is_err());
+    }
+    #[test]
+    fn threshold_attestation_passes_when_quorum_exactly_met() {
+        let (id_a, vk_a, sk_a) = ed25519_entry("key-a");
+        let (id_b, vk_b, sk_b) = ed25519_entry("key-b");
+        let mut trust = TrustStore::new();
+        trust.insert(id_a.clone(), vk_a);
+        trust.insert(id_b.clone(), vk_b);
+
+        let mut policies = PolicyStore::new();
+        policies.insert(
+            "world-1".to_string(),
+            RolePolicy {
+                key_ids: vec![id_a.clone(), id_b.clone()],
+                threshold: 2,
+            },
+        );
+
+        let json = plug_json(serde_json::json!({
+            "alg": "ed25519",
+            "signed": "baseline_merkle_root",
+            "signatures": [
+                { "key_id": id_a, "sig": sign_ed25519(&sk_a, BASELINE_ROOT.as_bytes()) },
+                { "key_id": id_b, "sig": sign_ed25519(&sk_b, BASELINE_ROOT.as_bytes()) },
+            ],
+        }));
+
+        let ctx = ingest_plug_verified_threshold(&json, &trust, &policies).unwrap();
+        assert_eq!(ctx.verified_by.len(), 2);
+    }
+
+    #[test]
+    fn threshold_attestation_fails_one_signer_short() {
+        let (id_a, vk_a, sk_a) = ed25519_entry("key-a");
+        let (id_b, vk_b, _sk_b) = ed25519_entry("key-b");
+        let mut trust = TrustStore::new();
+        trust.insert(id_a.clone(), vk_a);
+        trust.insert(id_b.clone(), vk_b);
+
+        let mut policies = PolicyStore::new();
+        policies.insert(
+            "world-1".to_string(),
+            RolePolicy {
+                key_ids: vec![id_a.clone(), id_b.clone()],
+                threshold: 2,
+            },
+        );
+
+        let json = plug_json(serde_json::json!({
+            "alg": "ed25519",
+            "signed": "baseline_merkle_root",
+            "signatures": [
+                { "key_id": id_a, "sig": sign_ed25519(&sk_a, BASELINE_ROOT.as_bytes()) },
+            ],
+        }));
+
+        assert!(ingest_plug_verified_threshold(&json, &trust, &policies).is_err());
+    }
+
+    #[test]
+    fn threshold_attestation_rejects_duplicate_key_id() {
+        let (id_a, vk_a, sk_a) = ed25519_entry("key-a");
+        let mut trust = TrustStore::new();
+        trust.insert(id_a.clone(), vk_a);
+
+        let mut policies = PolicyStore::new();
+        policies.insert(
+            "world-1".to_string(),
+            RolePolicy {
+                key_ids: vec![id_a.clone()],
+                threshold: 2,
+            },
+        );
+
+        let sig = sign_ed25519(&sk_a, BASELINE_ROOT.as_bytes());
+        let json = plug_json(serde_json::json!({
+            "alg": "ed25519",
+            "signed": "baseline_merkle_root",
+            "signatures": [
+                { "key_id": id_a.clone(), "sig": sig.clone() },
+                { "key_id": id_a, "sig": sig },
+            ],
+        }));
+
+        assert!(ingest_plug_verified_threshold(&json, &trust, &policies).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use base64::Engine;
+use blst::min_sig::{AggregatePublicKey, PublicKey, Signature};
+use blst::BLST_ERROR;
+use serde::Deserialize;
+
+use crate::ingestion::{ingest_plug, AgentContext};
+
+/// Domain-separation tag for hash-to-curve, fixed per the min-sig ciphersuite (signatures on
+/// G1, public keys on G2) used for invariant-set attestations.
+const DST: &[u8] = b"BLS_SIG_PSP_MERKLE_ROOT_V1";
+
+/// Maps `key_id` to the BLS12-381 public key an operator has chosen to trust.
+pub type BlsTrustStore = HashMap<String, PublicKey>;
+
+#[derive(Debug, Deserialize)]
+struct BlsAttestation {
+    alg: String,
+    signed: String,
+    agg_sig: String,
+    key_ids: Vec<String>,
+}
+
+/// Verifies a single aggregate BLS signature against the aggregate of `pubkeys`, checking the
+/// pairing equation `e(sig, g2) == e(H(root), aggpk)`. Attestation size stays constant
+/// regardless of how many kernels contributed a signature.
+pub fn verify_bls_attestation(
+    root: &str,
+    agg_sig: &[u8],
+    pubkeys: &[PublicKey],
+) -> Result<bool, String> {
+    if pubkeys.is_empty() {
+        return Err("no contributing signers".to_string());
+    }
+
+    let sig = Signature::from_bytes(agg_sig)
+        .map_err(|e| format!("malformed aggregate signature: {:?}", e))?;
+    if sig.validate(true).is_err() {
+        return Err("aggregate signature failed group check".to_string());
+    }
+
+    let pubkey_refs: Vec<&PublicKey> = pubkeys.iter().collect();
+    let agg_pk = AggregatePublicKey::aggregate(&pubkey_refs, true)
+        .map_err(|e| format!("failed to aggregate public keys: {:?}", e))?;
+    let pk = agg_pk.to_public_key();
+
+    let result = sig.verify(true, root.as_bytes(), DST, &[], &pk, true);
+    Ok(result == BLST_ERROR::BLST_SUCCESS)
+}
+
+/// Like `ingest_plug_verified`, but for worlds attested with a single aggregate BLS signature
+/// over `key_ids` instead of per-signer Ed25519/ECDSA entries. Duplicate `key_ids` are rejected
+/// outright: BLS signatures are additively homomorphic, so a single signer's signature could
+/// otherwise be doubled and replayed under a repeated `key_id` to falsely claim a second signer.
+pub fn ingest_plug_verified_bls(json: &str, trust: &BlsTrustStore) -> anyhow::Result<AgentContext> {
+    let mut ctx = ingest_plug(json)?;
+
+    let attestation: BlsAttestation = serde_json::from_value(ctx.invariants.attestation.clone())
+        .context("malformed attestation")?;
+    if attestation.alg != "bls12_381-min-sig" {
+        anyhow::bail!("unsupported attestation.alg: {}", attestation.alg);
+    }
+    if attestation.signed != "baseline_merkle_root" {
+        anyhow::bail!("unsupported attestation.signed value: {}", attestation.signed);
+    }
+    if attestation.key_ids.is_empty() {
+        anyhow::bail!("attestation carries no contributing key_ids");
+    }
+    {
+        let mut seen = std::collections::HashSet::new();
+        for key_id in &attestation.key_ids {
+            if !seen.insert(key_id.clone()) {
+                anyhow::bail!("duplicate attestation key_id: {}", key_id);
+            }
+        }
+    }
+
+    let mut pubkeys = Vec::with_capacity(attestation.key_ids.len());
+    for key_id in &attestation.key_ids {
+        let pk = trust
+            .get(key_id)
+            .ok_or_else(|| anyhow::anyhow!("no trusted BLS key for key_id {}", key_id))?;
+        pubkeys.push(*pk);
+    }
+
+    let agg_sig = base64::engine::general_purpose::STANDARD
+        .decode(&attestation.agg_sig)
+        .context("attestation.agg_sig is not valid base64")?;
+
+    if !verify_bls_attestation(&ctx.psp, &agg_sig, &pubkeys)
+        .map_err(|e| anyhow::anyhow!("{}", e))?
+    {
+        anyhow::bail!(
+            "invalid aggregate BLS attestation for world_id {}",
+            ctx.world_id
+        );
+    }
+
+    ctx.verified_by = attestation.key_ids;
+    Ok(ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{plug_json, BASELINE_ROOT};
+    use blst::min_sig::{AggregateSignature, SecretKey};
+
+    fn keypair(seed: u8) -> SecretKey {
+        let ikm = [seed; 32];
+        SecretKey::key_gen(&ikm, &[]).unwrap()
+    }
+
+    #[test]
+    fn ingest_plug_verified_bls_accepts_single_signer() {
+        let sk = keypair(1);
+        let pk = sk.sk_to_pk();
+        let sig = sk.sign(BASELINE_ROOT.as_bytes(), DST, &[]);
+
+        let mut trust = BlsTrustStore::new();
+        trust.insert("key-a".to_string(), pk);
+
+        let json = plug_json(serde_json::json!({
+            "alg": "bls12_381-min-sig",
+            "signed": "baseline_merkle_root",
+            "agg_sig": base64::engine::general_purpose::STANDARD.encode(sig.to_bytes()),
+            "key_ids": ["key-a"],
+        }));
+
+        let ctx = ingest_plug_verified_bls(&json, &trust).unwrap();
+        assert_eq!(ctx.verified_by, vec!["key-a".to_string()]);
+    }
+
+    #[test]
+    fn ingest_plug_verified_bls_rejects_duplicate_key_id_doubled_signature() {
+        let sk = keypair(2);
+        let pk = sk.sk_to_pk();
+        let sig = sk.sign(BASELINE_ROOT.as_bytes(), DST, &[]);
+
+        let mut trust = BlsTrustStore::new();
+        trust.insert("key-a".to_string(), pk);
+
+        let doubled = AggregateSignature::aggregate(&[&sig, &sig], true)
+            .unwrap()
+            .to_signature();
+
+        let json = plug_json(serde_json::json!({
+            "alg": "bls12_381-min-sig",
+            "signed": "baseline_merkle_root",
+            "agg_sig": base64::engine::general_purpose::STANDARD.encode(doubled.to_bytes()),
+            "key_ids": ["key-a", "key-a"],
+        }));
+
+        assert!(ingest_plug_verified_bls(&json, &trust).is_err());
+    }
+
+    #[test]
+    fn ingest_plug_verified_bls_accepts_two_distinct_signers() {
+        let sk_a = keypair(3);
+        let sk_b = keypair(4);
+        let pk_a = sk_a.sk_to_pk();
+        let pk_b = sk_b.sk_to_pk();
+        let sig_a = sk_a.sign(BASELINE_ROOT.as_bytes(), DST, &[]);
+        let sig_b = sk_b.sign(BASELINE_ROOT.as_bytes(), DST, &[]);
+        let agg = AggregateSignature::aggregate(&[&sig_a, &sig_b], true)
+            .unwrap()
+            .to_signature();
+
+        let mut trust = BlsTrustStore::new();
+        trust.insert("key-a".to_string(), pk_a);
+        trust.insert("key-b".to_string(), pk_b);
+
+        let json = plug_json(serde_json::json!({
+            "alg": "bls12_381-min-sig",
+            "signed": "baseline_merkle_root",
+            "agg_sig": base64::engine::general_purpose::STANDARD.encode(agg.to_bytes()),
+            "key_ids": ["key-a", "key-b"],
+        }));
+
+        let ctx = ingest_plug_verified_bls(&json, &trust).unwrap();
+        assert_eq!(ctx.verified_by, vec!["key-a".to_string(), "key-b".to_string()]);
+    }
+
+    #[test]
+    fn ingest_plug_verified_bls_rejects_tampered_root() {
+        let sk = keypair(5);
+        let pk = sk.sk_to_pk();
+        let sig = sk.sign(BASELINE_ROOT.as_bytes(), DST, &[]);
+
+        let mut trust = BlsTrustStore::new();
+        trust.insert("key-a".to_string(), pk);
+
+        let json = plug_json(serde_json::json!({
+            "alg": "bls12_381-min-sig",
+            "signed": "baseline_merkle_root",
+            "agg_sig": base64::engine::general_purpose::STANDARD.encode(sig.to_bytes()),
+            "key_ids": ["key-a"],
+        }));
+        let tampered = json.replace("aa\"", "ab\"");
+
+        assert!(ingest_plug_verified_bls(&tampered, &trust).is_err());
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+
+use crate::ingestion::AgentContext;
+
+const SHA_TAG_PREFIX: &str = "sha256:";
+
+fn bytes_to_sha_tag(bytes: &[u8; 32]) -> String {
+    let mut s = String::from(SHA_TAG_PREFIX);
+    for b in bytes {
+        s.push_str(&format!("{:02x}", b));
+    }
+    s
+}
+
+/// Fetches the bytes a `CapsuleRef.uri` refers to. Implementations are selected by URI scheme
+/// and registered at runtime, so embedders can add transports beyond the built-ins.
+pub trait SchemeHandler: Send + Sync {
+    fn fetch(&self, uri: &str) -> anyhow::Result<Box<dyn Read>>;
+}
+
+struct FileHandler;
+
+impl SchemeHandler for FileHandler {
+    fn fetch(&self, uri: &str) -> anyhow::Result<Box<dyn Read>> {
+        let path = uri
+            .strip_prefix("file://")
+            .ok_or_else(|| anyhow::anyhow!("not a file:// uri: {}", uri))?;
+        let file = std::fs::File::open(path).with_context(|| format!("opening {}", path))?;
+        Ok(Box::new(file))
+    }
+}
+
+struct HttpsHandler;
+
+impl SchemeHandler for HttpsHandler {
+    fn fetch(&self, uri: &str) -> anyhow::Result<Box<dyn Read>> {
+        let resp = ureq::get(uri)
+            .call()
+            .with_context(|| format!("fetching {}", uri))?;
+        Ok(Box::new(resp.into_reader()))
+    }
+}
+
+/// Resolves content-addressed `sha256:<hex>` URIs against a local content store keyed by URI,
+/// since the URI itself carries no transport location. `CapsuleResolver::new` registers it for
+/// the `sha256` scheme only; `ipfs://` URIs are not routed to it. The store
+/// is shared with the owning `CapsuleResolver` via `put_content`, since the handler itself has
+/// no way to be populated from outside the resolver.
+struct ContentAddressedHandler {
+    store: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl SchemeHandler for ContentAddressedHandler {
+    fn fetch(&self, uri: &str) -> anyhow::Result<Box<dyn Read>> {
+        let bytes = self
+            .store
+            .lock()
+            .unwrap()
+            .get(uri)
+            .ok_or_else(|| anyhow::anyhow!("no content-addressed entry for {}", uri))?
+            .clone();
+        Ok(Box::new(std::io::Cursor::new(bytes)))
+    }
+}
+
+fn scheme_of(uri: &str) -> anyhow::Result<&str> {
+    uri.split_once(':')
+        .map(|(scheme, _)| scheme)
+        .ok_or_else(|| anyhow::anyhow!("capsule_ref.uri has no scheme: {}", uri))
+}
+
+/// Registry of `SchemeHandler`s, keyed by URI scheme (`file`, `https`, `sha256`, `ipfs`, ...).
+pub struct CapsuleResolver {
+    handlers: HashMap<String, Box<dyn SchemeHandler>>,
+    content_store: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl CapsuleResolver {
+    /// Starts with only the content-addressed (`sha256:`) scheme registered. `capsule_ref.uri`
+    /// is never bound into any attestation signature (`signed_message` only covers
+    /// `baseline_merkle_root` or `world_id||capsule_digest`), so a verified plug can still carry
+    /// an attacker-chosen URI. `sha256:` is safe to enable unconditionally because it only reads
+    /// from this in-process store (`put_content`); `file://`/`https://` fetch the URI itself —
+    /// before the digest check can reject anything — which is an SSRF / local-file-read primitive
+    /// if enabled by default. Call `allow_file_scheme`/`allow_https_scheme` to opt in once the
+    /// caller has another way to trust `capsule_ref.uri` (e.g. an allowlisted prefix).
+    pub fn new() -> Self {
+        let content_store = Arc::new(Mutex::new(HashMap::new()));
+        let mut handlers: HashMap<String, Box<dyn SchemeHandler>> = HashMap::new();
+        handlers.insert(
+            "sha256".to_string(),
+            Box::new(ContentAddressedHandler {
+                store: Arc::clone(&content_store),
+            }),
+        );
+        Self {
+            handlers,
+            content_store,
+        }
+    }
+
+    /// Registers (or overrides) the handler for `scheme`, so embedders can add transports.
+    pub fn register(&mut self, scheme: impl Into<String>, handler: Box<dyn SchemeHandler>) {
+        self.handlers.insert(scheme.into(), handler);
+    }
+
+    /// Opts into the built-in `file://` handler. Unguarded, this lets any plug with a valid
+    /// attestation (none of which cover `capsule_ref.uri`) make this process read an arbitrary
+    /// local path, so only call this when the caller has independently decided `capsule_ref.uri`
+    /// is trustworthy (e.g. it's restricted to a known directory).
+    pub fn allow_file_scheme(&mut self) {
+        self.handlers.insert("file".to_string(), Box::new(FileHandler));
+    }
+
+    /// Opts into the built-in `https://` handler. Unguarded, this lets any plug with a valid
+    /// attestation (none of which cover `capsule_ref.uri`) make this process issue a request to
+    /// an arbitrary URL (SSRF), so only call this when the caller has independently decided
+    /// `capsule_ref.uri` is trustworthy (e.g. it's restricted to an allowlisted host).
+    pub fn allow_https_scheme(&mut self) {
+        self.handlers.insert("https".to_string(), Box::new(HttpsHandler));
+    }
+
+    /// Populates the built-in content-addressed (`sha256:`) store with `uri`'s bytes, so the
+    /// `sha256` scheme registered by `new` has something to serve. This is the only way to feed
+    /// that handler, since the URI itself carries no fetchable transport location.
+    pub fn put_content(&mut self, uri: impl Into<String>, bytes: Vec<u8>) {
+        self.content_store.lock().unwrap().insert(uri.into(), bytes);
+    }
+
+    /// Fetches `ctx.capsule_ref`'s bytes via the registered handler, hashing each chunk as it
+    /// arrives and forwarding it straight to `sink`, then rejects the result unless the digest
+    /// matches `ctx.capsule_digest`. Never buffers the whole capsule in memory — only a fixed
+    /// 64 KiB chunk at a time — so large-capsule callers can pass a disk-backed `sink` (e.g. a
+    /// `File`) instead of holding the full payload in a `Vec`. Because the digest is only known
+    /// once the stream is exhausted, treat `sink`'s contents as untrusted until this returns
+    /// `Ok`; a mismatch still leaves whatever was written in place for the caller to discard.
+    pub fn resolve_to_writer(&self, ctx: &AgentContext, sink: &mut dyn Write) -> anyhow::Result<()> {
+        let capsule_ref = ctx
+            .capsule_ref
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("plug has no capsule_ref to resolve"))?;
+
+        let scheme = scheme_of(&capsule_ref.uri)?;
+        let handler = self
+            .handlers
+            .get(scheme)
+            .ok_or_else(|| anyhow::anyhow!("no SchemeHandler registered for scheme {}", scheme))?;
+
+        let mut reader = handler.fetch(&capsule_ref.uri)?;
+        let mut hasher = Sha256::new();
+        let mut buf = [0u8; 64 * 1024];
+        loop {
+            let n = reader.read(&mut buf).context("reading capsule bytes")?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            sink.write_all(&buf[..n]).context("writing capsule bytes to sink")?;
+        }
+
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&hasher.finalize());
+        let tag = bytes_to_sha_tag(&digest);
+        if tag != ctx.capsule_digest {
+            anyhow::bail!(
+                "capsule digest mismatch: expected {}, got {}",
+                ctx.capsule_digest,
+                tag
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Fetches and verifies `ctx.capsule_ref`'s bytes, returning them as a single buffer.
+    /// Convenience wrapper around `resolve_to_writer` for callers that don't mind holding the
+    /// full capsule in memory; large-capsule callers should use `resolve_to_writer` with a
+    /// disk-backed sink instead.
+    pub fn resolve(&self, ctx: &AgentContext) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.resolve_to_writer(ctx, &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl Default for CapsuleResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fetches and verifies `ctx.capsule_ref`'s bytes via `resolver`'s registered handlers. This
+/// turns the otherwise-inert `capsule_ref` into a verified content-fetch path, analogous to how
+/// target files are retrieved and checked against trusted hashes. `&CapsuleResolver::default()`
+/// only supports `sha256:` content pre-populated via `put_content`; callers that want
+/// `file://`/`https://` or other schemes must build their own `CapsuleResolver`, explicitly
+/// opting into `allow_file_scheme`/`allow_https_scheme`/`register` as appropriate.
+pub fn resolve_capsule(ctx: &AgentContext, resolver: &CapsuleResolver) -> anyhow::Result<Vec<u8>> {
+    resolver.resolve(ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingestion::{CapsuleRef, InvariantSetV1, KernelTokenV1, MerkleAlgo};
+
+    fn digest_of(bytes: &[u8]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(bytes);
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&hasher.finalize());
+        bytes_to_sha_tag(&out)
+    }
+
+    fn ctx_for(uri: &str, capsule_digest: String) -> AgentContext {
+        AgentContext {
+            world_id: "world-1".to_string(),
+            capsule_digest: capsule_digest.clone(),
+            engine_hash: "sha256:00".to_string(),
+            psp: "sha256:00".to_string(),
+            kernel_token: KernelTokenV1 {
+                schema_version: "cie_v2_plug.v1".to_string(),
+                world_id: "world-1".to_string(),
+                capsule_digest: capsule_digest.clone(),
+                merkle_root: "sha256:00".to_string(),
+                tick_count: 0,
+                merkle_algo: None,
+            },
+            invariants: InvariantSetV1 {
+                schema_version: "cie_v2_plug.v1".to_string(),
+                world_id: "world-1".to_string(),
+                capsule_digest,
+                merkle_root: "sha256:00".to_string(),
+                tick_count: 0,
+                invariants: serde_json::json!({}),
+                attestation: serde_json::json!({}),
+            },
+            capsule_ref: Some(CapsuleRef {
+                kind: "content-addressed".to_string(),
+                uri: uri.to_string(),
+            }),
+            merkle_algo: MerkleAlgo::V1,
+            verified_by: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn content_addressed_handler_resolves_put_content() {
+        let bytes = b"hello capsule".to_vec();
+        let uri = digest_of(&bytes);
+
+        let mut resolver = CapsuleResolver::new();
+        resolver.put_content(uri.clone(), bytes.clone());
+
+        let ctx = ctx_for(&uri, uri.clone());
+        let resolved = resolve_capsule(&ctx, &resolver).unwrap();
+        assert_eq!(resolved, bytes);
+    }
+
+    #[test]
+    fn content_addressed_handler_rejects_digest_mismatch() {
+        let bytes = b"hello capsule".to_vec();
+        let uri = digest_of(&bytes);
+
+        let mut resolver = CapsuleResolver::new();
+        resolver.put_content(uri.clone(), bytes);
+
+        let wrong_digest = digest_of(b"different content");
+        let ctx = ctx_for(&uri, wrong_digest);
+        assert!(resolve_capsule(&ctx, &resolver).is_err());
+    }
+
+    #[test]
+    fn content_addressed_handler_fails_lookup_when_unpopulated() {
+        let bytes = b"hello capsule".to_vec();
+        let uri = digest_of(&bytes);
+
+        let resolver = CapsuleResolver::new();
+        let ctx = ctx_for(&uri, uri.clone());
+        assert!(resolve_capsule(&ctx, &resolver).is_err());
+    }
+
+    #[test]
+    fn custom_registered_handler_is_reachable_through_resolve_capsule() {
+        struct StaticHandler(Vec<u8>);
+        impl SchemeHandler for StaticHandler {
+            fn fetch(&self, _uri: &str) -> anyhow::Result<Box<dyn Read>> {
+                Ok(Box::new(std::io::Cursor::new(self.0.clone())))
+            }
+        }
+
+        let bytes = b"custom scheme payload".to_vec();
+        let digest = digest_of(&bytes);
+
+        let mut resolver = CapsuleResolver::new();
+        resolver.register("custom", Box::new(StaticHandler(bytes.clone())));
+
+        let ctx = ctx_for("custom://anything", digest);
+        let resolved = resolve_capsule(&ctx, &resolver).unwrap();
+        assert_eq!(resolved, bytes);
+    }
+
+    #[test]
+    fn default_resolver_does_not_register_file_or_https() {
+        let resolver = CapsuleResolver::new();
+        let ctx = ctx_for("file:///etc/passwd", "sha256:00".to_string());
+        assert!(resolve_capsule(&ctx, &resolver).is_err());
+
+        let ctx = ctx_for("https://169.254.169.254/", "sha256:00".to_string());
+        assert!(resolve_capsule(&ctx, &resolver).is_err());
+    }
+
+    #[test]
+    fn file_scheme_only_works_after_explicit_opt_in() {
+        let mut resolver = CapsuleResolver::new();
+        resolver.allow_file_scheme();
+
+        let mut path = std::env::temp_dir();
+        path.push(format!("capsule-test-{:?}", std::thread::current().id()));
+        let bytes = b"file scheme payload".to_vec();
+        std::fs::write(&path, &bytes).unwrap();
+        let digest = digest_of(&bytes);
+
+        let ctx = ctx_for(&format!("file://{}", path.display()), digest);
+        let resolved = resolve_capsule(&ctx, &resolver).unwrap();
+        assert_eq!(resolved, bytes);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn resolve_to_writer_streams_into_a_disk_backed_sink() {
+        let bytes = b"hello streamed capsule".to_vec();
+        let uri = digest_of(&bytes);
+
+        let mut resolver = CapsuleResolver::new();
+        resolver.put_content(uri.clone(), bytes.clone());
+        let ctx = ctx_for(&uri, uri.clone());
+
+        let mut out_path = std::env::temp_dir();
+        out_path.push(format!(
+            "capsule-test-out-{:?}",
+            std::thread::current().id()
+        ));
+        let mut file = std::fs::File::create(&out_path).unwrap();
+        resolver.resolve_to_writer(&ctx, &mut file).unwrap();
+        drop(file);
+
+        let written = std::fs::read(&out_path).unwrap();
+        assert_eq!(written, bytes);
+        std::fs::remove_file(&out_path).unwrap();
+    }
+}
@@ -7,6 +7,7 @@ pub struct KernelTokenV1 {
     pub capsule_digest: String,
     pub merkle_root: String,
     pub tick_count: u64,
+    pub merkle_algo: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -37,6 +38,28 @@ pub struct CieV2PlugV1 {
     pub invariant_set: InvariantSetV1,
     pub extractor_version: String,
     pub capsule_ref: Option<CapsuleRef>,
+    pub merkle_algo: Option<String>,
+}
+
+/// Merkle tree construction algorithm a plug's roots were built with. `V3` is the RFC
+/// 6962-recursive packing (`psp_merkle_root_v3_from_tick_hashes`); a `merkle_root` is only
+/// provable with `psp_consistency_proof`/`psp_verify_consistency` when `merkle_algo` is `V3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MerkleAlgo {
+    V1,
+    V2,
+    V3,
+}
+
+impl MerkleAlgo {
+    fn parse(raw: Option<&str>) -> anyhow::Result<Self> {
+        match raw {
+            None | Some("v1") => Ok(MerkleAlgo::V1),
+            Some("v2") => Ok(MerkleAlgo::V2),
+            Some("v3") => Ok(MerkleAlgo::V3),
+            Some(other) => anyhow::bail!("unsupported merkle_algo: {}", other),
+        }
+    }
 }
 
 pub struct AgentContext {
@@ -47,6 +70,10 @@ pub struct AgentContext {
     pub kernel_token: KernelTokenV1,
     pub invariants: InvariantSetV1,
     pub capsule_ref: Option<CapsuleRef>,
+    pub merkle_algo: MerkleAlgo,
+    /// key_ids of the signers whose attestation satisfied the verifier that produced this
+    /// context. Empty for plain `ingest_plug`, since no attestation check ran.
+    pub verified_by: Vec<String>,
 }
 
 pub fn ingest_plug(json: &str) -> anyhow::Result<AgentContext> {
@@ -65,6 +92,12 @@ pub fn ingest_plug(json: &str) -> anyhow::Result<AgentContext> {
         anyhow::bail!("invariant_set.merkle_root != baseline_merkle_root");
     }
 
+    let merkle_algo = MerkleAlgo::parse(
+        plug.merkle_algo
+            .as_deref()
+            .or(plug.kernel_token.merkle_algo.as_deref()),
+    )?;
+
     Ok(AgentContext {
         world_id: plug.world_id,
         capsule_digest: plug.capsule_digest,
@@ -73,5 +106,7 @@ pub fn ingest_plug(json: &str) -> anyhow::Result<AgentContext> {
         kernel_token: plug.kernel_token,
         invariants: plug.invariant_set,
         capsule_ref: plug.capsule_ref,
+        merkle_algo,
+        verified_by: Vec::new(),
     })
 }
@@ -0,0 +1,7 @@
+pub mod attestation;
+pub mod bls_attestation;
+pub mod capsule;
+pub mod ingestion;
+
+#[cfg(test)]
+pub(crate) mod test_support;
@@ -0,0 +1,37 @@
+//! Shared fixtures for the attestation-verifying modules' unit tests, so `attestation` and
+//! `bls_attestation` don't each carry their own copy of the plug JSON skeleton to drift apart as
+//! the schema evolves.
+
+pub(crate) const BASELINE_ROOT: &str =
+    "sha256:0000000000000000000000000000000000000000000000000000000000aa";
+
+pub(crate) fn plug_json(attestation: serde_json::Value) -> String {
+    serde_json::json!({
+        "schema_version": "cie_v2_plug.v1",
+        "world_id": "world-1",
+        "capsule_digest": "sha256:00000000000000000000000000000000000000000000000000000000000b",
+        "engine_hash": "sha256:000000000000000000000000000000000000000000000000000000000000c",
+        "baseline_merkle_root": BASELINE_ROOT,
+        "kernel_token": {
+            "schema_version": "cie_v2_plug.v1",
+            "world_id": "world-1",
+            "capsule_digest": "sha256:00000000000000000000000000000000000000000000000000000000000b",
+            "merkle_root": BASELINE_ROOT,
+            "tick_count": 3,
+            "merkle_algo": null,
+        },
+        "invariant_set": {
+            "schema_version": "cie_v2_plug.v1",
+            "world_id": "world-1",
+            "capsule_digest": "sha256:00000000000000000000000000000000000000000000000000000000000b",
+            "merkle_root": BASELINE_ROOT,
+            "tick_count": 3,
+            "invariants": {},
+            "attestation": attestation,
+        },
+        "extractor_version": "v1",
+        "capsule_ref": null,
+        "merkle_algo": null,
+    })
+    .to_string()
+}
@@ -1,6 +1,7 @@
 use sha2::{Digest, Sha256};
 
 const DOMAIN: &str = "PSP.MerkleRoot.v1";
+const DOMAIN_V2: &str = "PSP.MerkleRoot.v2";
 const SHA_TAG_PREFIX: &str = "sha256:";
 
 fn sha_tag_to_bytes(tag: &str) -> Result<[u8; 32], String> {
@@ -34,6 +35,22 @@ fn sha256(preimage: &[u8]) -> [u8; 32] {
     arr
 }
 
+/// Which side of the parent a proof node occupies during recomputation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+fn combine(domain_bytes: &[u8], left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut preimage = Vec::with_capacity(domain_bytes.len() + 1 + 32 + 32);
+    preimage.extend_from_slice(domain_bytes);
+    preimage.push(0u8);
+    preimage.extend_from_slice(left);
+    preimage.extend_from_slice(right);
+    sha256(&preimage)
+}
+
 pub fn psp_merkle_root_from_tick_hashes(tick_hashes: &[String]) -> Result<String, String> {
     if tick_hashes.is_empty() {
         return Ok(format!("{}{}", SHA_TAG_PREFIX, "0".repeat(64)));
@@ -46,17 +63,138 @@ pub fn psp_merkle_root_from_tick_hashes(tick_hashes: &[String]) -> Result<String
     }
 
     while layer.len() > 1 {
-        let mut next: Vec<[u8; 32]> = Vec::with_capacity((layer.len() + 1) / 2);
+        let mut next: Vec<[u8; 32]> = Vec::with_capacity(layer.len().div_ceil(2));
+        let mut i = 0usize;
+        while i < layer.len() {
+            let left = layer[i];
+            let right = if i + 1 < layer.len() { layer[i + 1] } else { layer[i] };
+            next.push(combine(domain_bytes, &left, &right));
+            i += 2;
+        }
+        layer = next;
+    }
+
+    Ok(bytes_to_sha_tag(&layer[0]))
+}
+
+fn build_layers(tick_hashes: &[String]) -> Result<Vec<Vec<[u8; 32]>>, String> {
+    let domain_bytes = DOMAIN.as_bytes();
+    let mut leaves: Vec<[u8; 32]> = Vec::with_capacity(tick_hashes.len());
+    for t in tick_hashes {
+        leaves.push(sha_tag_to_bytes(t)?);
+    }
+
+    let mut layers = vec![leaves];
+    while layers.last().unwrap().len() > 1 {
+        let layer = layers.last().unwrap();
+        let mut next: Vec<[u8; 32]> = Vec::with_capacity(layer.len().div_ceil(2));
+        let mut i = 0usize;
+        while i < layer.len() {
+            let left = layer[i];
+            let right = if i + 1 < layer.len() { layer[i + 1] } else { layer[i] };
+            next.push(combine(domain_bytes, &left, &right));
+            i += 2;
+        }
+        layers.push(next);
+    }
+    Ok(layers)
+}
+
+/// Returns the audit path (sibling hash + its side relative to the node being folded)
+/// needed to recompute the root from `tick_hashes[index]` alone, mirroring the odd-node
+/// duplication rule used by `psp_merkle_root_from_tick_hashes`.
+pub fn psp_merkle_inclusion_proof(
+    tick_hashes: &[String],
+    index: usize,
+) -> Result<Vec<(Side, String)>, String> {
+    if index >= tick_hashes.len() {
+        return Err(format!(
+            "index {} out of bounds for {} tick hashes",
+            index,
+            tick_hashes.len()
+        ));
+    }
+
+    let layers = build_layers(tick_hashes)?;
+    let mut proof = Vec::new();
+    let mut idx = index;
+    for layer in &layers[..layers.len() - 1] {
+        let (sibling_idx, side) = if idx.is_multiple_of(2) {
+            let sibling_idx = if idx + 1 < layer.len() { idx + 1 } else { idx };
+            (sibling_idx, Side::Right)
+        } else {
+            (idx - 1, Side::Left)
+        };
+        proof.push((side, bytes_to_sha_tag(&layer[sibling_idx])));
+        idx /= 2;
+    }
+
+    Ok(proof)
+}
+
+/// Recomputes the root by folding `leaf` up through `proof` and checks it against `root`.
+pub fn psp_verify_inclusion_proof(
+    leaf: &str,
+    _index: usize,
+    proof: &[(Side, String)],
+    root: &str,
+) -> Result<bool, String> {
+    let domain_bytes = DOMAIN.as_bytes();
+    let mut current = sha_tag_to_bytes(leaf)?;
+
+    for (side, sibling) in proof {
+        let sibling_bytes = sha_tag_to_bytes(sibling)?;
+        current = match side {
+            Side::Left => combine(domain_bytes, &sibling_bytes, &current),
+            Side::Right => combine(domain_bytes, &current, &sibling_bytes),
+        };
+    }
+
+    Ok(bytes_to_sha_tag(&current) == root)
+}
+
+/// Hashes a leaf under `DOMAIN_V2`'s leaf tag (`DOMAIN_V2 || 0x00 || leaf`), so an interior
+/// node's digest can never be replayed as a leaf.
+fn leaf_hash_v2(leaf: &[u8; 32]) -> [u8; 32] {
+    let domain_bytes = DOMAIN_V2.as_bytes();
+    let mut preimage = Vec::with_capacity(domain_bytes.len() + 1 + 32);
+    preimage.extend_from_slice(domain_bytes);
+    preimage.push(0u8);
+    preimage.extend_from_slice(leaf);
+    sha256(&preimage)
+}
+
+/// Combines two nodes under `DOMAIN_V2`'s internal tag (`DOMAIN_V2 || 0x01 || left || right`).
+fn combine_v2(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let domain_bytes = DOMAIN_V2.as_bytes();
+    let mut preimage = Vec::with_capacity(domain_bytes.len() + 1 + 32 + 32);
+    preimage.extend_from_slice(domain_bytes);
+    preimage.push(1u8);
+    preimage.extend_from_slice(left);
+    preimage.extend_from_slice(right);
+    sha256(&preimage)
+}
+
+/// Like `psp_merkle_root_from_tick_hashes`, but with RFC 6962-style leaf/internal domain
+/// separation (`DOMAIN_V2 || 0x00 || leaf` for leaves, `DOMAIN_V2 || 0x01 || left || right`
+/// for internal nodes) so an interior digest can never be replayed as a leaf.
+pub fn psp_merkle_root_v2_from_tick_hashes(tick_hashes: &[String]) -> Result<String, String> {
+    if tick_hashes.is_empty() {
+        return Ok(format!("{}{}", SHA_TAG_PREFIX, "0".repeat(64)));
+    }
+
+    let mut layer: Vec<[u8; 32]> = Vec::with_capacity(tick_hashes.len());
+    for t in tick_hashes {
+        layer.push(leaf_hash_v2(&sha_tag_to_bytes(t)?));
+    }
+
+    while layer.len() > 1 {
+        let mut next: Vec<[u8; 32]> = Vec::with_capacity(layer.len().div_ceil(2));
         let mut i = 0usize;
         while i < layer.len() {
             let left = layer[i];
             let right = if i + 1 < layer.len() { layer[i + 1] } else { layer[i] };
-            let mut preimage = Vec::with_capacity(domain_bytes.len() + 1 + 32 + 32);
-            preimage.extend_from_slice(domain_bytes);
-            preimage.push(0u8);
-            preimage.extend_from_slice(&left);
-            preimage.extend_from_slice(&right);
-            next.push(sha256(&preimage));
+            next.push(combine_v2(&left, &right));
             i += 2;
         }
         layer = next;
@@ -64,3 +202,411 @@ pub fn psp_merkle_root_from_tick_hashes(tick_hashes: &[String]) -> Result<String
 
     Ok(bytes_to_sha_tag(&layer[0]))
 }
+
+fn build_layers_v2(tick_hashes: &[String]) -> Result<Vec<Vec<[u8; 32]>>, String> {
+    let mut leaves: Vec<[u8; 32]> = Vec::with_capacity(tick_hashes.len());
+    for t in tick_hashes {
+        leaves.push(leaf_hash_v2(&sha_tag_to_bytes(t)?));
+    }
+
+    let mut layers = vec![leaves];
+    while layers.last().unwrap().len() > 1 {
+        let layer = layers.last().unwrap();
+        let mut next: Vec<[u8; 32]> = Vec::with_capacity(layer.len().div_ceil(2));
+        let mut i = 0usize;
+        while i < layer.len() {
+            let left = layer[i];
+            let right = if i + 1 < layer.len() { layer[i + 1] } else { layer[i] };
+            next.push(combine_v2(&left, &right));
+            i += 2;
+        }
+        layers.push(next);
+    }
+    Ok(layers)
+}
+
+/// Like `psp_merkle_inclusion_proof`, but against a `psp_merkle_root_v2_from_tick_hashes` root:
+/// `v2` hashes leaves and internal nodes under distinct domain tags, so a `v1` proof can't be
+/// recomputed against a `v2` root (and vice versa) — a plug that selects `merkle_algo: "v2"`
+/// needs this v2-aware pair instead.
+pub fn psp_merkle_inclusion_proof_v2(
+    tick_hashes: &[String],
+    index: usize,
+) -> Result<Vec<(Side, String)>, String> {
+    if index >= tick_hashes.len() {
+        return Err(format!(
+            "index {} out of bounds for {} tick hashes",
+            index,
+            tick_hashes.len()
+        ));
+    }
+
+    let layers = build_layers_v2(tick_hashes)?;
+    let mut proof = Vec::new();
+    let mut idx = index;
+    for layer in &layers[..layers.len() - 1] {
+        let (sibling_idx, side) = if idx.is_multiple_of(2) {
+            let sibling_idx = if idx + 1 < layer.len() { idx + 1 } else { idx };
+            (sibling_idx, Side::Right)
+        } else {
+            (idx - 1, Side::Left)
+        };
+        proof.push((side, bytes_to_sha_tag(&layer[sibling_idx])));
+        idx /= 2;
+    }
+
+    Ok(proof)
+}
+
+/// Like `psp_verify_inclusion_proof`, but folds `leaf` up using the `v2` leaf/internal domain
+/// tags, matching a proof built by `psp_merkle_inclusion_proof_v2` against a `v2` root.
+pub fn psp_verify_inclusion_proof_v2(
+    leaf: &str,
+    _index: usize,
+    proof: &[(Side, String)],
+    root: &str,
+) -> Result<bool, String> {
+    let mut current = leaf_hash_v2(&sha_tag_to_bytes(leaf)?);
+
+    for (side, sibling) in proof {
+        let sibling_bytes = sha_tag_to_bytes(sibling)?;
+        current = match side {
+            Side::Left => combine_v2(&sibling_bytes, &current),
+            Side::Right => combine_v2(&current, &sibling_bytes),
+        };
+    }
+
+    Ok(bytes_to_sha_tag(&current) == root)
+}
+
+fn largest_power_of_two_less_than(n: usize) -> usize {
+    let mut k = 1;
+    while k * 2 < n {
+        k *= 2;
+    }
+    k
+}
+
+/// Recursive Merkle tree hash (RFC 6962 `MTH`): splits at the largest power of two `k <
+/// leaves.len()` and combines `MTH(D[0:k])` with `MTH(D[k:n])`. Unlike the bottom-up,
+/// duplicate-last-per-level folding `psp_merkle_root_from_tick_hashes`/`_v2_` use, this packing
+/// keeps power-of-two-sized subtrees stable as the tree grows, which is what makes a short
+/// append-only consistency proof possible at all. This is the tree shape behind
+/// `psp_merkle_root_v3_from_tick_hashes` and the only shape `psp_consistency_proof` /
+/// `psp_verify_consistency` support.
+fn mth(leaves: &[[u8; 32]], domain_bytes: &[u8]) -> [u8; 32] {
+    if leaves.len() == 1 {
+        return leaves[0];
+    }
+    let k = largest_power_of_two_less_than(leaves.len());
+    combine(
+        domain_bytes,
+        &mth(&leaves[..k], domain_bytes),
+        &mth(&leaves[k..], domain_bytes),
+    )
+}
+
+/// Like `psp_merkle_root_from_tick_hashes`, but packed with the RFC 6962 recursive split (`mth`)
+/// instead of a bottom-up per-level fold. A `merkle_root` produced by this function (tagged
+/// `merkle_algo: "v3"`) is the only kind `psp_consistency_proof`/`psp_verify_consistency` can
+/// prove anything about: the bottom-up fold `v1` uses only happens to agree with this packing
+/// when the tick count is a power of two, so consistency proofs are not meaningful against a
+/// `v1` root for any other tick count, and `v2` hashes under its own leaf/internal domain tags,
+/// so they are never meaningful against a `v2` root.
+pub fn psp_merkle_root_v3_from_tick_hashes(tick_hashes: &[String]) -> Result<String, String> {
+    if tick_hashes.is_empty() {
+        return Ok(format!("{}{}", SHA_TAG_PREFIX, "0".repeat(64)));
+    }
+
+    let mut leaves = Vec::with_capacity(tick_hashes.len());
+    for t in tick_hashes {
+        leaves.push(sha_tag_to_bytes(t)?);
+    }
+
+    Ok(bytes_to_sha_tag(&mth(&leaves, DOMAIN.as_bytes())))
+}
+
+/// Builds the audit path for `PROOF(m, D[n])` per the RFC 6962 consistency-proof recurrence:
+/// split the range at the largest power of two `k < n`, recurse into whichever side still
+/// straddles the old/new boundary, and append the other side's subtree hash directly.
+fn subproof(m: usize, leaves: &[[u8; 32]], b: bool, domain_bytes: &[u8], out: &mut Vec<[u8; 32]>) {
+    let n = leaves.len();
+    if m == n {
+        if !b {
+            out.push(mth(leaves, domain_bytes));
+        }
+        return;
+    }
+
+    let k = largest_power_of_two_less_than(n);
+    if m <= k {
+        subproof(m, &leaves[..k], b, domain_bytes, out);
+        out.push(mth(&leaves[k..], domain_bytes));
+    } else {
+        subproof(m - k, &leaves[k..], false, domain_bytes, out);
+        out.push(mth(&leaves[..k], domain_bytes));
+    }
+}
+
+/// Produces the minimal set of subtree hashes needed to recompute both `MTH(old_tick_hashes)`
+/// and `MTH(new_tick_hashes)`, proving the old tree is an append-only prefix of the new one.
+/// `MTH` here is the recursive RFC 6962 packing (see `mth`), so roots used with this proof must
+/// come from `psp_merkle_root_v3_from_tick_hashes` rather than from `v1`/`v2`'s per-level fold.
+pub fn psp_consistency_proof(
+    old_tick_hashes: &[String],
+    new_tick_hashes: &[String],
+) -> Result<Vec<String>, String> {
+    let m = old_tick_hashes.len();
+    let n = new_tick_hashes.len();
+    if m > n {
+        return Err(format!(
+            "old tree ({} ticks) is larger than new tree ({} ticks)",
+            m, n
+        ));
+    }
+    if old_tick_hashes != &new_tick_hashes[..m] {
+        return Err("old tick hashes are not a prefix of new tick hashes".to_string());
+    }
+    if m == 0 || m == n {
+        return Ok(Vec::new());
+    }
+
+    let domain_bytes = DOMAIN.as_bytes();
+    let mut leaves = Vec::with_capacity(n);
+    for t in new_tick_hashes {
+        leaves.push(sha_tag_to_bytes(t)?);
+    }
+
+    let mut out = Vec::new();
+    subproof(m, &leaves, true, domain_bytes, &mut out);
+    Ok(out.iter().map(bytes_to_sha_tag).collect())
+}
+
+/// Reconstructs both `old_root` and `new_root` by walking `proof` through the same recurrence
+/// used to build it, substituting `old_root` wherever the proof relies on a subtree the
+/// verifier already trusts.
+fn verify_subproof(
+    m: usize,
+    n: usize,
+    b: bool,
+    proof: &mut std::slice::Iter<[u8; 32]>,
+    old_root: &[u8; 32],
+    domain_bytes: &[u8],
+) -> Result<([u8; 32], [u8; 32]), String> {
+    if m == n {
+        if b {
+            return Ok((*old_root, *old_root));
+        }
+        let v = *proof.next().ok_or("consistency proof is too short")?;
+        return Ok((v, v));
+    }
+
+    let k = largest_power_of_two_less_than(n);
+    if m <= k {
+        let (old_h, new_h_left) = verify_subproof(m, k, b, proof, old_root, domain_bytes)?;
+        let right = *proof.next().ok_or("consistency proof is too short")?;
+        let new_h = combine(domain_bytes, &new_h_left, &right);
+        Ok((old_h, new_h))
+    } else {
+        let (old_h_right, new_h_right) =
+            verify_subproof(m - k, n - k, false, proof, old_root, domain_bytes)?;
+        let left = *proof.next().ok_or("consistency proof is too short")?;
+        let old_h = combine(domain_bytes, &left, &old_h_right);
+        let new_h = combine(domain_bytes, &left, &new_h_right);
+        Ok((old_h, new_h))
+    }
+}
+
+/// Verifies that `new_root` (over `new_size` ticks) is an append-only extension of `old_root`
+/// (over `old_size` ticks), using `proof` as produced by `psp_consistency_proof`.
+pub fn psp_verify_consistency(
+    old_root: &str,
+    old_size: usize,
+    new_root: &str,
+    new_size: usize,
+    proof: &[String],
+) -> Result<bool, String> {
+    if old_size > new_size {
+        return Err(format!(
+            "old_size {} is larger than new_size {}",
+            old_size, new_size
+        ));
+    }
+    if old_size == 0 {
+        return Ok(proof.is_empty());
+    }
+    if old_size == new_size {
+        return Ok(proof.is_empty() && old_root == new_root);
+    }
+    if proof.is_empty() {
+        return Err("consistency proof is empty".to_string());
+    }
+
+    let domain_bytes = DOMAIN.as_bytes();
+    let old_root_bytes = sha_tag_to_bytes(old_root)?;
+    let new_root_bytes = sha_tag_to_bytes(new_root)?;
+    let mut proof_bytes = Vec::with_capacity(proof.len());
+    for p in proof {
+        proof_bytes.push(sha_tag_to_bytes(p)?);
+    }
+
+    let mut iter = proof_bytes.iter();
+    let (computed_old, computed_new) = verify_subproof(
+        old_size,
+        new_size,
+        true,
+        &mut iter,
+        &old_root_bytes,
+        domain_bytes,
+    )?;
+    if iter.next().is_some() {
+        return Err("consistency proof has trailing elements".to_string());
+    }
+
+    Ok(computed_old == old_root_bytes && computed_new == new_root_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(n: u8) -> String {
+        bytes_to_sha_tag(&sha256(&[n]))
+    }
+
+    fn ticks(n: usize) -> Vec<String> {
+        (0..n).map(|i| tick(i as u8)).collect()
+    }
+
+    fn tamper(tag: &str) -> String {
+        let mut bytes = sha_tag_to_bytes(tag).unwrap();
+        bytes[0] ^= 0xff;
+        bytes_to_sha_tag(&bytes)
+    }
+
+    #[test]
+    fn inclusion_proof_round_trips_for_odd_leaf_count() {
+        let hashes = ticks(5);
+        let root = psp_merkle_root_from_tick_hashes(&hashes).unwrap();
+
+        for i in 0..hashes.len() {
+            let proof = psp_merkle_inclusion_proof(&hashes, i).unwrap();
+            assert!(psp_verify_inclusion_proof(&hashes[i], i, &proof, &root).unwrap());
+        }
+    }
+
+    #[test]
+    fn inclusion_proof_rejects_tampered_leaf() {
+        let hashes = ticks(4);
+        let root = psp_merkle_root_from_tick_hashes(&hashes).unwrap();
+        let proof = psp_merkle_inclusion_proof(&hashes, 2).unwrap();
+
+        let tampered_leaf = tamper(&hashes[2]);
+        assert!(!psp_verify_inclusion_proof(&tampered_leaf, 2, &proof, &root).unwrap());
+    }
+
+    #[test]
+    fn inclusion_proof_rejects_tampered_sibling() {
+        let hashes = ticks(5);
+        let root = psp_merkle_root_from_tick_hashes(&hashes).unwrap();
+        let mut proof = psp_merkle_inclusion_proof(&hashes, 0).unwrap();
+        proof[0].1 = tamper(&proof[0].1);
+
+        assert!(!psp_verify_inclusion_proof(&hashes[0], 0, &proof, &root).unwrap());
+    }
+
+    #[test]
+    fn v2_root_differs_from_v1_for_same_ticks() {
+        let hashes = ticks(3);
+        let v1 = psp_merkle_root_from_tick_hashes(&hashes).unwrap();
+        let v2 = psp_merkle_root_v2_from_tick_hashes(&hashes).unwrap();
+        assert_ne!(v1, v2);
+    }
+
+    #[test]
+    fn v2_inclusion_proof_round_trips_for_odd_leaf_count() {
+        let hashes = ticks(5);
+        let root = psp_merkle_root_v2_from_tick_hashes(&hashes).unwrap();
+
+        for i in 0..hashes.len() {
+            let proof = psp_merkle_inclusion_proof_v2(&hashes, i).unwrap();
+            assert!(psp_verify_inclusion_proof_v2(&hashes[i], i, &proof, &root).unwrap());
+        }
+    }
+
+    #[test]
+    fn v2_inclusion_proof_rejects_tampered_leaf() {
+        let hashes = ticks(4);
+        let root = psp_merkle_root_v2_from_tick_hashes(&hashes).unwrap();
+        let proof = psp_merkle_inclusion_proof_v2(&hashes, 2).unwrap();
+
+        let tampered_leaf = tamper(&hashes[2]);
+        assert!(!psp_verify_inclusion_proof_v2(&tampered_leaf, 2, &proof, &root).unwrap());
+    }
+
+    #[test]
+    fn v1_inclusion_proof_does_not_verify_against_v2_root() {
+        let hashes = ticks(5);
+        let v2_root = psp_merkle_root_v2_from_tick_hashes(&hashes).unwrap();
+        let v1_proof = psp_merkle_inclusion_proof(&hashes, 0).unwrap();
+
+        assert!(!psp_verify_inclusion_proof(&hashes[0], 0, &v1_proof, &v2_root).unwrap());
+    }
+
+    #[test]
+    fn consistency_proof_round_trips_for_odd_tree_growth() {
+        let old_hashes = ticks(3);
+        let new_hashes = ticks(7);
+        let old_root = psp_merkle_root_v3_from_tick_hashes(&old_hashes).unwrap();
+        let new_root = psp_merkle_root_v3_from_tick_hashes(&new_hashes).unwrap();
+
+        let proof = psp_consistency_proof(&old_hashes, &new_hashes).unwrap();
+        assert!(psp_verify_consistency(
+            &old_root,
+            old_hashes.len(),
+            &new_root,
+            new_hashes.len(),
+            &proof
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn consistency_proof_rejects_non_prefix_growth() {
+        let old_hashes = ticks(3);
+        let mut new_hashes = ticks(7);
+        new_hashes[1] = tamper(&new_hashes[1]);
+
+        assert!(psp_consistency_proof(&old_hashes, &new_hashes).is_err());
+    }
+
+    #[test]
+    fn consistency_proof_rejects_tampered_entry() {
+        let old_hashes = ticks(3);
+        let new_hashes = ticks(7);
+        let old_root = psp_merkle_root_v3_from_tick_hashes(&old_hashes).unwrap();
+        let new_root = psp_merkle_root_v3_from_tick_hashes(&new_hashes).unwrap();
+
+        let mut proof = psp_consistency_proof(&old_hashes, &new_hashes).unwrap();
+        let last = proof.len() - 1;
+        proof[last] = tamper(&proof[last]);
+
+        assert!(!psp_verify_consistency(
+            &old_root,
+            old_hashes.len(),
+            &new_root,
+            new_hashes.len(),
+            &proof
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn consistency_proof_is_empty_for_equal_sizes() {
+        let hashes = ticks(4);
+        let root = psp_merkle_root_v3_from_tick_hashes(&hashes).unwrap();
+        let proof = psp_consistency_proof(&hashes, &hashes).unwrap();
+        assert!(proof.is_empty());
+        assert!(psp_verify_consistency(&root, hashes.len(), &root, hashes.len(), &proof).unwrap());
+    }
+}